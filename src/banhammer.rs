@@ -1,32 +1,42 @@
+use crate::buckets::{BucketConfig, GasBuckets, GasBucketsConfig, LeakyBucket};
 use crate::de::{RelayerInput, Token, TransactionError};
+use crate::events::{BanEvent, BanEventSink, BanTransition};
 use ethereum_types::Address;
 use serde::{
     de::{self, Error, Visitor},
-    Deserialize, Deserializer,
+    Deserialize, Deserializer, Serialize,
 };
 use std::{
     collections::HashMap,
     fmt::{self},
     net::IpAddr,
-    time::{Duration, Instant},
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+/// Upper bound on `BanProgress::revert_log`'s length. Without a cap a
+/// chatty-but-never-quite-banned key's revert log would grow unboundedly for
+/// as long as the process runs, and every entry would be re-serialized on
+/// each periodic snapshot write (see `crate::snapshot::SnapshotSchedule`).
+const MAX_REVERT_LOG_ENTRIES: usize = 32;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct BanProgress {
-    incorrect_nonce: u32,
-    max_gas: u32,
-    revert: Vec<String>,
-    excessive_gas: u32,
+    incorrect_nonce: LeakyBucket,
+    max_gas: LeakyBucket,
+    revert: LeakyBucket,
+    revert_log: Vec<String>,
+    gas: GasBuckets,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct BanList {
     pub clients: HashMap<IpAddr, UserClient>,
     pub tokens: HashMap<Token, UserToken>,
     pub froms: HashMap<Address, UserFrom>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BanKind {
     IncorrectNonce,
     MaxGas,
@@ -34,37 +44,168 @@ pub enum BanKind {
     ExcessiveGas(u32),
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+/// A point-in-time view of a key's `BanProgress`, safe to hand to the
+/// control-plane server since it reduces each `LeakyBucket` to its fill
+/// level instead of its (non-serializable) `Instant`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BanProgressSnapshot {
+    pub incorrect_nonce: u64,
+    pub max_gas: u64,
+    pub revert_count: usize,
+    pub near_gas: u64,
+    pub eth_gas: u64,
+    pub free_gas: u64,
+}
+
+/// Serializes an `Instant` as whole seconds since the Unix epoch, so
+/// `banned_at` survives a restart instead of resetting to "now" (`Instant`
+/// itself carries no fixed epoch and can't be serialized directly).
+fn serialize_banned_at<S>(instant: &Instant, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    let elapsed = Instant::now().saturating_duration_since(*instant);
+    let epoch_secs = SystemTime::now()
+        .checked_sub(elapsed)
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default()
+        .as_secs();
+    serializer.serialize_u64(epoch_secs)
+}
+
+/// Inverse of `serialize_banned_at`: rebuilds an `Instant` that is as far in
+/// the past (relative to now) as the persisted epoch-seconds value was when
+/// it was saved.
+fn deserialize_banned_at<'de, D>(deserializer: D) -> Result<Instant, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let epoch_secs = u64::deserialize(deserializer)?;
+    let banned_at = UNIX_EPOCH + Duration::from_secs(epoch_secs);
+    let elapsed = SystemTime::now()
+        .duration_since(banned_at)
+        .unwrap_or_default();
+    Ok(Instant::now()
+        .checked_sub(elapsed)
+        .unwrap_or_else(Instant::now))
+}
+
+impl BanProgress {
+    pub fn snapshot(&self) -> BanProgressSnapshot {
+        BanProgressSnapshot {
+            incorrect_nonce: self.incorrect_nonce.value(),
+            max_gas: self.max_gas.value(),
+            revert_count: self.revert_log.len(),
+            near_gas: self.gas.near_gas.value(),
+            eth_gas: self.gas.eth_gas.value(),
+            free_gas: self.gas.free_gas.value(),
+        }
+    }
+
+    /// Appends `msg` to `revert_log`, dropping the oldest entry first if
+    /// already at `MAX_REVERT_LOG_ENTRIES`, so the log stays a bounded
+    /// recent window instead of growing for the lifetime of the process.
+    fn push_revert(&mut self, msg: String) {
+        if self.revert_log.len() >= MAX_REVERT_LOG_ENTRIES {
+            self.revert_log.remove(0);
+        }
+        self.revert_log.push(msg);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserClient {
     tokens: Vec<Token>,
     froms: Vec<Address>,
     ban_progress: BanProgress,
     banned: Option<BanKind>,
+    /// When `banned` was last set; used together with `strikes` to compute
+    /// this ban's expiry (see `Banhammer::tick`). Persisted as epoch seconds
+    /// so a restart doesn't reset an in-progress ban's clock.
+    #[serde(
+        serialize_with = "serialize_banned_at",
+        deserialize_with = "deserialize_banned_at"
+    )]
+    banned_at: Instant,
+    /// Number of times this key has been banned; each additional ban doubles
+    /// its retention so repeat offenders stay banned progressively longer.
+    strikes: u32,
+}
+
+impl Default for UserClient {
+    fn default() -> Self {
+        Self {
+            tokens: Vec::new(),
+            froms: Vec::new(),
+            ban_progress: BanProgress::default(),
+            banned: None,
+            banned_at: Instant::now(),
+            strikes: 0,
+        }
+    }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserFrom {
     clients: Vec<IpAddr>,
     tokens: Vec<Token>,
     ban_progress: BanProgress,
     banned: Option<BanKind>,
+    #[serde(
+        serialize_with = "serialize_banned_at",
+        deserialize_with = "deserialize_banned_at"
+    )]
+    banned_at: Instant,
+    strikes: u32,
+}
+
+impl Default for UserFrom {
+    fn default() -> Self {
+        Self {
+            clients: Vec::new(),
+            tokens: Vec::new(),
+            ban_progress: BanProgress::default(),
+            banned: None,
+            banned_at: Instant::now(),
+            strikes: 0,
+        }
+    }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserToken {
     clients: Vec<IpAddr>,
     froms: Vec<Address>,
     ban_progress: BanProgress,
     banned: Option<BanKind>,
+    #[serde(
+        serialize_with = "serialize_banned_at",
+        deserialize_with = "deserialize_banned_at"
+    )]
+    banned_at: Instant,
+    strikes: u32,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+impl Default for UserToken {
+    fn default() -> Self {
+        Self {
+            clients: Vec::new(),
+            froms: Vec::new(),
+            ban_progress: BanProgress::default(),
+            banned: None,
+            banned_at: Instant::now(),
+            strikes: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum UserKind {
     Client(UserClient),
     From(UserFrom),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BannedUserKind {
     Client(IpAddr),
     Token(Token),
@@ -141,20 +282,22 @@ struct User {
     client: IpAddr,
     from: Address,
     token: Option<Token>,
+    near_gas: u64,
+    eth_gas: u64,
+    free_gas: bool,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
     #[serde(deserialize_with = "deserialize_duration")]
     timeframe: Duration,
-    incorrect_nonce_threshold: u32,
-    max_gas_threshold: u32,
-    revert_threshold: u32,
-    // excessive_gas_threshold: u32, // TODO
+    incorrect_nonce_bucket: BucketConfig,
+    max_gas_bucket: BucketConfig,
+    revert_bucket: BucketConfig,
+    gas_buckets: GasBucketsConfig,
     token_multiplier: u32,
 }
 
-#[derive(Debug)]
 pub struct Banhammer {
     next_check: Duration,
     user_clients: HashMap<IpAddr, UserClient>,
@@ -162,6 +305,38 @@ pub struct Banhammer {
     user_tokens: HashMap<Token, UserToken>,
     ban_list: BanList,
     config: Config,
+    /// Sinks notified of every ban/unban transition in place of `println!`.
+    sinks: Vec<Box<dyn BanEventSink>>,
+    /// Set by `new_with_snapshot`; drives periodic persistence from `tick`.
+    snapshot: Option<crate::snapshot::SnapshotSchedule>,
+}
+
+impl fmt::Debug for Banhammer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Banhammer")
+            .field("next_check", &self.next_check)
+            .field("user_clients", &self.user_clients)
+            .field("user_froms", &self.user_froms)
+            .field("user_tokens", &self.user_tokens)
+            .field("ban_list", &self.ban_list)
+            .field("config", &self.config)
+            .field("sinks", &self.sinks.len())
+            .field("snapshot", &self.snapshot.is_some())
+            .finish()
+    }
+}
+
+/// The full persistable state of a `Banhammer`: the ban list plus in-flight
+/// progress for every tracked client, address and token. Deliberately
+/// excludes `Config` (reloaded from configuration on every start) and
+/// `next_check` (a runtime-only scheduling cursor). Snapshotted and restored
+/// by `crate::snapshot::SnapshotStore`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BanhammerState {
+    pub user_clients: HashMap<IpAddr, UserClient>,
+    pub user_froms: HashMap<Address, UserFrom>,
+    pub user_tokens: HashMap<Token, UserToken>,
+    pub ban_list: BanList,
 }
 
 fn check_error_ban(
@@ -169,72 +344,360 @@ fn check_error_ban(
     config: &Config,
     token: Option<&Token>,
     maybe_error: Option<&TransactionError>,
-) -> bool {
-    let error = if let Some(error) = maybe_error {
-        error
+    now: Instant,
+) -> Option<BanKind> {
+    let error = maybe_error?;
+    let multiplier = if token.is_some() {
+        config.token_multiplier as u64
     } else {
-        return false;
+        1
     };
     match error {
         TransactionError::ErrIncorrectNonce => {
-            let threshold = {
-                if token.is_some() {
-                    config.incorrect_nonce_threshold * config.token_multiplier
-                } else {
-                    config.incorrect_nonce_threshold
-                }
-            };
-            ban_progress.incorrect_nonce += 1;
-            ban_progress.incorrect_nonce >= threshold
+            let bucket_config = config.incorrect_nonce_bucket.scaled(multiplier);
+            ban_progress
+                .incorrect_nonce
+                .record(&bucket_config, 1, now)
+                .then_some(BanKind::IncorrectNonce)
         }
         TransactionError::MaxGas => {
-            let threshold = {
-                if token.is_some() {
-                    config.max_gas_threshold * config.token_multiplier
-                } else {
-                    config.max_gas_threshold
-                }
-            };
-            ban_progress.max_gas += 1;
-            ban_progress.max_gas >= threshold
+            let bucket_config = config.max_gas_bucket.scaled(multiplier);
+            ban_progress
+                .max_gas
+                .record(&bucket_config, 1, now)
+                .then_some(BanKind::MaxGas)
         }
         TransactionError::Revert(msg) => {
-            let threshold = {
-                if token.is_some() {
-                    config.revert_threshold * config.token_multiplier
-                } else {
-                    config.revert_threshold
-                }
-            };
-            ban_progress.revert.push(msg.clone());
-            ban_progress.max_gas >= threshold
+            let bucket_config = config.revert_bucket.scaled(multiplier);
+            ban_progress.push_revert(msg.clone());
+            ban_progress
+                .revert
+                .record(&bucket_config, 1, now)
+                .then_some(BanKind::Revert(msg.clone()))
+        }
+        TransactionError::Relayer(_) => None,
+    }
+}
+
+/// Feeds a transaction's gas spend into its `near_gas`/`eth_gas`/`free_gas`
+/// buckets and reports the overflowing bucket's fill level, if any, as
+/// `BanKind::ExcessiveGas`.
+fn check_gas_ban(
+    gas: &mut GasBuckets,
+    config: &Config,
+    token: Option<&Token>,
+    near_gas: u64,
+    eth_gas: u64,
+    free_gas: bool,
+    now: Instant,
+) -> Option<BanKind> {
+    let multiplier = if token.is_some() {
+        config.token_multiplier as u64
+    } else {
+        1
+    };
+
+    if free_gas {
+        let bucket_config = config.gas_buckets.free_gas.scaled(multiplier);
+        if gas.free_gas.record(&bucket_config, 1, now) {
+            return Some(BanKind::ExcessiveGas(gas.free_gas.value() as u32));
         }
-        TransactionError::Relayer(_) => false,
     }
+
+    let near_bucket_config = config.gas_buckets.near_gas.scaled(multiplier);
+    if gas.near_gas.record(&near_bucket_config, near_gas, now) {
+        return Some(BanKind::ExcessiveGas(gas.near_gas.value() as u32));
+    }
+
+    let eth_bucket_config = config.gas_buckets.eth_gas.scaled(multiplier);
+    if gas.eth_gas.record(&eth_bucket_config, eth_gas, now) {
+        return Some(BanKind::ExcessiveGas(gas.eth_gas.value() as u32));
+    }
+
+    None
+}
+
+/// Always records the transaction's gas spend, regardless of whether it also
+/// carries an error, so a client can't dodge gas accounting by pairing every
+/// high-gas call with a trivial error. An error-based ban takes priority over
+/// a gas-based one if both trip on the same event.
+#[allow(clippy::too_many_arguments)]
+fn check_bans(
+    ban_progress: &mut BanProgress,
+    config: &Config,
+    token: Option<&Token>,
+    maybe_error: Option<&TransactionError>,
+    near_gas: u64,
+    eth_gas: u64,
+    free_gas: bool,
+    now: Instant,
+) -> Option<BanKind> {
+    let error_kind = check_error_ban(ban_progress, config, token, maybe_error, now);
+    let gas_kind = check_gas_ban(&mut ban_progress.gas, config, token, near_gas, eth_gas, free_gas, now);
+    error_kind.or(gas_kind)
+}
+
+/// Shared read access to the ban bookkeeping that `UserClient`, `UserFrom`
+/// and `UserToken` carry, so expiry can be computed generically over all
+/// three instead of being triplicated.
+trait BannedEntry {
+    fn banned(&self) -> Option<&BanKind>;
+    fn banned_at(&self) -> Instant;
+    fn strikes(&self) -> u32;
+}
+
+impl BannedEntry for UserClient {
+    fn banned(&self) -> Option<&BanKind> {
+        self.banned.as_ref()
+    }
+
+    fn banned_at(&self) -> Instant {
+        self.banned_at
+    }
+
+    fn strikes(&self) -> u32 {
+        self.strikes
+    }
+}
+
+impl BannedEntry for UserFrom {
+    fn banned(&self) -> Option<&BanKind> {
+        self.banned.as_ref()
+    }
+
+    fn banned_at(&self) -> Instant {
+        self.banned_at
+    }
+
+    fn strikes(&self) -> u32 {
+        self.strikes
+    }
+}
+
+impl BannedEntry for UserToken {
+    fn banned(&self) -> Option<&BanKind> {
+        self.banned.as_ref()
+    }
+
+    fn banned_at(&self) -> Instant {
+        self.banned_at
+    }
+
+    fn strikes(&self) -> u32 {
+        self.strikes
+    }
+}
+
+/// The configured retention for the bucket that produced `kind`.
+/// `ExcessiveGas` isn't tied to a single bucket, so it uses the longest of
+/// the three gas buckets' retentions.
+fn retention_for(config: &Config, kind: &BanKind) -> Duration {
+    let secs = match kind {
+        BanKind::IncorrectNonce => config.incorrect_nonce_bucket.retention,
+        BanKind::MaxGas => config.max_gas_bucket.retention,
+        BanKind::Revert(_) => config.revert_bucket.retention,
+        BanKind::ExcessiveGas(_) => config
+            .gas_buckets
+            .near_gas
+            .retention
+            .max(config.gas_buckets.eth_gas.retention)
+            .max(config.gas_buckets.free_gas.retention),
+    };
+    Duration::from_secs(secs)
+}
+
+/// Doubles `base` for every ban beyond the first, so a key that keeps
+/// re-offending stays banned progressively longer instead of cycling in and
+/// out on a fixed retention.
+fn escalated_retention(base: Duration, strikes: u32) -> Duration {
+    let doublings = strikes.saturating_sub(1).min(32);
+    let multiplier = 1u32.checked_shl(doublings).unwrap_or(u32::MAX);
+    base.checked_mul(multiplier).unwrap_or(Duration::MAX)
 }
 
 impl Banhammer {
     pub fn new(config: Config) -> Self {
+        Self::with_state(config, BanhammerState::default())
+    }
+
+    /// Builds a `Banhammer` seeded with previously-persisted `state` (see
+    /// `crate::snapshot::SnapshotStore`), so a restart doesn't lose bans or
+    /// in-flight progress.
+    pub fn with_state(config: Config, state: BanhammerState) -> Self {
         Self {
             next_check: config.timeframe,
-            user_clients: HashMap::default(),
-            user_froms: HashMap::default(),
-            user_tokens: HashMap::default(),
-            ban_list: BanList::default(),
+            user_clients: state.user_clients,
+            user_froms: state.user_froms,
+            user_tokens: state.user_tokens,
+            ban_list: state.ban_list,
             config,
+            sinks: Vec::new(),
+            snapshot: None,
+        }
+    }
+
+    /// Like `new`, but loads `snapshot_path` through a `SnapshotStore` first,
+    /// starting fresh if no valid snapshot exists, and thereafter saves the
+    /// current state back to `snapshot_path` every `config.timeframe` from
+    /// `tick`, so a later restart has something to load.
+    pub fn new_with_snapshot(config: Config, snapshot_path: impl Into<PathBuf>) -> Self {
+        let mut schedule = crate::snapshot::SnapshotSchedule::new(snapshot_path, config.timeframe);
+        let state = schedule.load();
+        let mut banhammer = Self::with_state(config, state);
+        banhammer.snapshot = Some(schedule);
+        banhammer
+    }
+
+    /// Registers `sink` to be notified of every subsequent ban/unban
+    /// transition, e.g. a `WebhookSink` forwarding them to an alerting
+    /// endpoint.
+    pub fn with_sink(mut self, sink: Box<dyn BanEventSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Snapshots the current ban list and in-flight progress for persistence.
+    pub fn state(&self) -> BanhammerState {
+        BanhammerState {
+            user_clients: self.user_clients.clone(),
+            user_froms: self.user_froms.clone(),
+            user_tokens: self.user_tokens.clone(),
+            ban_list: self.ban_list.clone(),
         }
     }
 
-    pub fn tick(&mut self, time: Instant) {
+    /// Advances the scheduling cursor and expires any ban whose (possibly
+    /// escalated) retention has elapsed, returning the keys that were just
+    /// unbanned so callers can log or emit unban events.
+    pub fn tick(&mut self, time: Instant) -> Vec<BannedUserKind> {
+        // Leaky buckets drain continuously from their own `last_update`, so
+        // there is no longer a fixed window to reset here.
         if time.elapsed() > self.next_check {
-            for (_, client) in self.user_clients.iter_mut() {
-                client.ban_progress.excessive_gas = 0;
-                client.ban_progress.max_gas = 0;
-                client.ban_progress.incorrect_nonce = 0;
-                client.ban_progress.revert = Vec::new();
-            }
             self.next_check += self.config.timeframe;
         }
+
+        let now = Instant::now();
+        let mut expired = Vec::new();
+
+        let expired_clients: Vec<IpAddr> = self
+            .ban_list
+            .clients
+            .iter()
+            .filter(|(_, user)| Self::ban_expired(&self.config, *user, now))
+            .map(|(ip, _)| *ip)
+            .collect();
+        for ip in expired_clients {
+            if let Some(mut user) = self.ban_list.clients.remove(&ip) {
+                let kind = user.banned.take();
+                user.ban_progress = BanProgress::default();
+                let event = kind.map(|kind| {
+                    BanEvent::new(
+                        BanTransition::Unbanned,
+                        BannedUserKind::Client(ip),
+                        kind,
+                        vec![ip],
+                        user.froms.clone(),
+                        user.tokens.clone(),
+                    )
+                });
+                self.user_clients.insert(ip, user);
+                if let Some(event) = event {
+                    self.notify_unban(&event);
+                }
+                expired.push(BannedUserKind::Client(ip));
+            }
+        }
+
+        let expired_froms: Vec<Address> = self
+            .ban_list
+            .froms
+            .iter()
+            .filter(|(_, user)| Self::ban_expired(&self.config, *user, now))
+            .map(|(address, _)| *address)
+            .collect();
+        for address in expired_froms {
+            if let Some(mut user) = self.ban_list.froms.remove(&address) {
+                let kind = user.banned.take();
+                user.ban_progress = BanProgress::default();
+                let event = kind.map(|kind| {
+                    BanEvent::new(
+                        BanTransition::Unbanned,
+                        BannedUserKind::From(address),
+                        kind,
+                        user.clients.clone(),
+                        vec![address],
+                        user.tokens.clone(),
+                    )
+                });
+                self.user_froms.insert(address, user);
+                if let Some(event) = event {
+                    self.notify_unban(&event);
+                }
+                expired.push(BannedUserKind::From(address));
+            }
+        }
+
+        let expired_tokens: Vec<Token> = self
+            .ban_list
+            .tokens
+            .iter()
+            .filter(|(_, user)| Self::ban_expired(&self.config, *user, now))
+            .map(|(token, _)| token.clone())
+            .collect();
+        for token in expired_tokens {
+            if let Some(mut user) = self.ban_list.tokens.remove(&token) {
+                let kind = user.banned.take();
+                user.ban_progress = BanProgress::default();
+                let event = kind.map(|kind| {
+                    BanEvent::new(
+                        BanTransition::Unbanned,
+                        BannedUserKind::Token(token.clone()),
+                        kind,
+                        user.clients.clone(),
+                        user.froms.clone(),
+                        vec![token.clone()],
+                    )
+                });
+                self.user_tokens.insert(token.clone(), user);
+                if let Some(event) = event {
+                    self.notify_unban(&event);
+                }
+                expired.push(BannedUserKind::Token(token));
+            }
+        }
+
+        if self.snapshot.is_some() {
+            let state = self.state();
+            if let Some(schedule) = &mut self.snapshot {
+                schedule.maybe_save(&state, now);
+            }
+        }
+
+        expired
+    }
+
+    fn notify_ban(&self, event: &BanEvent) {
+        for sink in &self.sinks {
+            sink.on_ban(event);
+        }
+    }
+
+    fn notify_unban(&self, event: &BanEvent) {
+        for sink in &self.sinks {
+            sink.on_unban(event);
+        }
+    }
+
+    /// A ban has expired once `now` is past `banned_at` by at least the
+    /// triggering `BanKind`'s configured retention, doubled once for every
+    /// strike beyond the first (see `escalated_retention`).
+    fn ban_expired<U: BannedEntry>(config: &Config, user: &U, now: Instant) -> bool {
+        let kind = match user.banned() {
+            Some(kind) => kind,
+            None => return false,
+        };
+        let retention = escalated_retention(retention_for(config, kind), user.strikes());
+        now.saturating_duration_since(user.banned_at()) >= retention
     }
 
     fn ban_progression(
@@ -242,32 +705,50 @@ impl Banhammer {
         user: &User,
         token: Option<&Token>,
         maybe_error: Option<&TransactionError>,
-        // gas: // TODO when added to relayer
-    ) -> (bool, bool, bool) {
-        // TODO excessive gas
-        let is_client_banned = if !self.ban_list.clients.contains_key(&user.client) {
+    ) -> (Option<BanKind>, Option<BanKind>, Option<BanKind>) {
+        let now = Instant::now();
+
+        let client_banned = if !self.ban_list.clients.contains_key(&user.client) {
             let client_progress = &mut self
                 .user_clients
                 .get_mut(&user.client)
                 .expect("`UserClient` missing.")
                 .ban_progress;
-            check_error_ban(client_progress, &self.config, token, maybe_error)
+            check_bans(
+                client_progress,
+                &self.config,
+                token,
+                maybe_error,
+                user.near_gas,
+                user.eth_gas,
+                user.free_gas,
+                now,
+            )
         } else {
-            false
+            None
         };
 
-        let is_from_banned = if !self.ban_list.froms.contains_key(&user.from) {
+        let from_banned = if !self.ban_list.froms.contains_key(&user.from) {
             let from_progress = &mut self
                 .user_froms
                 .get_mut(&user.from)
                 .expect("`UserFrom' missing.")
                 .ban_progress;
-            check_error_ban(from_progress, &self.config, token, maybe_error)
+            check_bans(
+                from_progress,
+                &self.config,
+                token,
+                maybe_error,
+                user.near_gas,
+                user.eth_gas,
+                user.free_gas,
+                now,
+            )
         } else {
-            false
+            None
         };
 
-        let is_token_banned = {
+        let token_banned = {
             if let Some(token) = token {
                 if !self.ban_list.tokens.contains_key(token) {
                     let token_progress = &mut self
@@ -275,16 +756,25 @@ impl Banhammer {
                         .get_mut(token)
                         .expect("'UserToken' missing.")
                         .ban_progress;
-                    check_error_ban(token_progress, &self.config, Some(token), maybe_error)
+                    check_bans(
+                        token_progress,
+                        &self.config,
+                        Some(token),
+                        maybe_error,
+                        user.near_gas,
+                        user.eth_gas,
+                        user.free_gas,
+                        now,
+                    )
                 } else {
-                    false
+                    None
                 }
             } else {
-                false
+                None
             }
         };
 
-        (is_client_banned, is_from_banned, is_token_banned)
+        (client_banned, from_banned, token_banned)
     }
 
     fn associate_with_user_client(
@@ -296,16 +786,16 @@ impl Banhammer {
         let user_client = self
             .user_clients
             .entry(client)
-            .or_insert_with(UserClient::default);
+            .or_default();
 
         if !user_client.froms.contains(&from) {
             user_client.froms.push(from)
         }
 
-        if let Some(token) = maybe_token {
-            if !user_client.tokens.contains(&token) {
-                user_client.tokens.push(token);
-            }
+        if let Some(token) = maybe_token
+            && !user_client.tokens.contains(&token)
+        {
+            user_client.tokens.push(token);
         }
     }
 
@@ -318,16 +808,16 @@ impl Banhammer {
         let user_from = self
             .user_froms
             .entry(from)
-            .or_insert_with(UserFrom::default);
+            .or_default();
 
         if !user_from.clients.contains(&client) {
             user_from.clients.push(client);
         }
 
-        if let Some(token) = maybe_token {
-            if !user_from.tokens.contains(&token) {
-                user_from.tokens.push(token);
-            }
+        if let Some(token) = maybe_token
+            && !user_from.tokens.contains(&token)
+        {
+            user_from.tokens.push(token);
         }
     }
 
@@ -335,7 +825,7 @@ impl Banhammer {
         let user_token = self
             .user_tokens
             .entry(token)
-            .or_insert_with(UserToken::default);
+            .or_default();
 
         if !user_token.clients.contains(&client) {
             user_token.clients.push(client);
@@ -352,6 +842,9 @@ impl Banhammer {
             client: input.client,
             from: input.params.from,
             token: input.token.clone(),
+            near_gas: input.near_gas,
+            eth_gas: input.eth_gas,
+            free_gas: input.free_gas,
         };
 
         self.associate_with_user_client(user.client, user.from, user.token.clone());
@@ -360,49 +853,335 @@ impl Banhammer {
             self.associate_with_user_token(token, user.client, user.from);
         }
 
-        let (is_client_banned, is_from_banned, is_token_banned) =
+        let (client_banned, from_banned, token_banned) =
             self.ban_progression(&user, user.token.as_ref(), maybe_error);
 
-        if is_client_banned {
-            println!(
-                "BANNED client: {}, reason: {:?}",
-                user.client,
-                maybe_error.expect("Error expected")
-            );
-            let user_client = self
+        if let Some(kind) = client_banned {
+            let mut user_client = self
                 .user_clients
                 .remove(&user.client)
                 .expect("`UserClient` missing.");
+            user_client.banned = Some(kind.clone());
+            user_client.banned_at = Instant::now();
+            user_client.strikes += 1;
+            let event = BanEvent::new(
+                BanTransition::Banned,
+                BannedUserKind::Client(user.client),
+                kind,
+                vec![user.client],
+                user_client.froms.clone(),
+                user_client.tokens.clone(),
+            );
             self.ban_list.clients.insert(user.client, user_client);
+            self.notify_ban(&event);
         }
-        if is_from_banned {
-            println!(
-                "BANNED from: {:?}, reason: {:?}",
-                user.from,
-                maybe_error.expect("Error expected")
-            );
-            let user_from = self
+        if let Some(kind) = from_banned {
+            let mut user_from = self
                 .user_froms
                 .remove(&user.from)
                 .expect("`UserFrom` missing.");
+            user_from.banned = Some(kind.clone());
+            user_from.banned_at = Instant::now();
+            user_from.strikes += 1;
+            let event = BanEvent::new(
+                BanTransition::Banned,
+                BannedUserKind::From(user.from),
+                kind,
+                user_from.clients.clone(),
+                vec![user.from],
+                user_from.tokens.clone(),
+            );
             self.ban_list.froms.insert(user.from, user_from);
+            self.notify_ban(&event);
         }
-        if is_token_banned {
+        if let Some(kind) = token_banned {
             let token = user.token.expect("'Token' missing.");
-            println!(
-                "BANNED token: {:?}, reason: {:?}",
-                token,
-                maybe_error.expect("Error expected")
-            );
-            let user_token = self
+            let mut user_token = self
                 .user_tokens
                 .remove(&token)
                 .expect("`UserToken` missing.");
+            user_token.banned = Some(kind.clone());
+            user_token.banned_at = Instant::now();
+            user_token.strikes += 1;
+            let event = BanEvent::new(
+                BanTransition::Banned,
+                BannedUserKind::Token(token.clone()),
+                kind,
+                user_token.clients.clone(),
+                user_token.froms.clone(),
+                vec![token.clone()],
+            );
             self.ban_list.tokens.insert(token, user_token);
+            self.notify_ban(&event);
         }
     }
 
     pub fn ban_list(&self) -> &BanList {
         &self.ban_list
     }
+
+    /// Enumerates every currently banned client, address and token.
+    pub fn banned_users(&self) -> Vec<BannedUserKind> {
+        let mut users: Vec<BannedUserKind> = self
+            .ban_list
+            .clients
+            .keys()
+            .copied()
+            .map(BannedUserKind::Client)
+            .collect();
+        users.extend(self.ban_list.froms.keys().copied().map(BannedUserKind::From));
+        users.extend(
+            self.ban_list
+                .tokens
+                .keys()
+                .cloned()
+                .map(BannedUserKind::Token),
+        );
+        users
+    }
+
+    /// Looks up `user`'s current ban progress, whether it is still active or
+    /// already banned.
+    pub fn progress(&self, user: &BannedUserKind) -> Option<BanProgressSnapshot> {
+        match user {
+            BannedUserKind::Client(ip) => self
+                .user_clients
+                .get(ip)
+                .or_else(|| self.ban_list.clients.get(ip))
+                .map(|user| user.ban_progress.snapshot()),
+            BannedUserKind::From(address) => self
+                .user_froms
+                .get(address)
+                .or_else(|| self.ban_list.froms.get(address))
+                .map(|user| user.ban_progress.snapshot()),
+            BannedUserKind::Token(token) => self
+                .user_tokens
+                .get(token)
+                .or_else(|| self.ban_list.tokens.get(token))
+                .map(|user| user.ban_progress.snapshot()),
+        }
+    }
+
+    /// Manually bans `user` with `kind`, bypassing the usual bucket
+    /// thresholds. Used by the control-plane server for surgical bans.
+    ///
+    /// If `user` is already banned, its `ban_list` entry (with its existing
+    /// `strikes` count and `froms`/`tokens` associations) is updated in
+    /// place rather than being replaced by a fresh default, so re-banning an
+    /// already-banned key still escalates retention instead of resetting it.
+    pub fn manual_ban(&mut self, user: BannedUserKind, kind: BanKind) {
+        let event = match &user {
+            BannedUserKind::Client(ip) => {
+                let mut client = self
+                    .ban_list
+                    .clients
+                    .remove(ip)
+                    .or_else(|| self.user_clients.remove(ip))
+                    .unwrap_or_default();
+                client.banned = Some(kind.clone());
+                client.banned_at = Instant::now();
+                client.strikes += 1;
+                let event = BanEvent::new(
+                    BanTransition::Banned,
+                    user.clone(),
+                    kind,
+                    vec![*ip],
+                    client.froms.clone(),
+                    client.tokens.clone(),
+                );
+                self.ban_list.clients.insert(*ip, client);
+                event
+            }
+            BannedUserKind::From(address) => {
+                let mut from = self
+                    .ban_list
+                    .froms
+                    .remove(address)
+                    .or_else(|| self.user_froms.remove(address))
+                    .unwrap_or_default();
+                from.banned = Some(kind.clone());
+                from.banned_at = Instant::now();
+                from.strikes += 1;
+                let event = BanEvent::new(
+                    BanTransition::Banned,
+                    user.clone(),
+                    kind,
+                    from.clients.clone(),
+                    vec![*address],
+                    from.tokens.clone(),
+                );
+                self.ban_list.froms.insert(*address, from);
+                event
+            }
+            BannedUserKind::Token(token) => {
+                let mut user_token = self
+                    .ban_list
+                    .tokens
+                    .remove(token)
+                    .or_else(|| self.user_tokens.remove(token))
+                    .unwrap_or_default();
+                user_token.banned = Some(kind.clone());
+                user_token.banned_at = Instant::now();
+                user_token.strikes += 1;
+                let event = BanEvent::new(
+                    BanTransition::Banned,
+                    user.clone(),
+                    kind,
+                    user_token.clients.clone(),
+                    user_token.froms.clone(),
+                    vec![token.clone()],
+                );
+                self.ban_list.tokens.insert(token.clone(), user_token);
+                event
+            }
+        };
+        self.notify_ban(&event);
+    }
+
+    /// Lifts a ban, returning `user` to active tracking with a clean
+    /// `BanProgress`. Returns `false` if `user` was not banned.
+    pub fn manual_unban(&mut self, user: &BannedUserKind) -> bool {
+        let event = match user {
+            BannedUserKind::Client(ip) => match self.ban_list.clients.remove(ip) {
+                Some(mut client) => {
+                    let kind = client.banned.take();
+                    client.ban_progress = BanProgress::default();
+                    let event = kind.map(|kind| {
+                        BanEvent::new(
+                            BanTransition::Unbanned,
+                            user.clone(),
+                            kind,
+                            vec![*ip],
+                            client.froms.clone(),
+                            client.tokens.clone(),
+                        )
+                    });
+                    self.user_clients.insert(*ip, client);
+                    event
+                }
+                None => None,
+            },
+            BannedUserKind::From(address) => match self.ban_list.froms.remove(address) {
+                Some(mut from) => {
+                    let kind = from.banned.take();
+                    from.ban_progress = BanProgress::default();
+                    let event = kind.map(|kind| {
+                        BanEvent::new(
+                            BanTransition::Unbanned,
+                            user.clone(),
+                            kind,
+                            from.clients.clone(),
+                            vec![*address],
+                            from.tokens.clone(),
+                        )
+                    });
+                    self.user_froms.insert(*address, from);
+                    event
+                }
+                None => None,
+            },
+            BannedUserKind::Token(token) => match self.ban_list.tokens.remove(token) {
+                Some(mut user_token) => {
+                    let kind = user_token.banned.take();
+                    user_token.ban_progress = BanProgress::default();
+                    let event = kind.map(|kind| {
+                        BanEvent::new(
+                            BanTransition::Unbanned,
+                            user.clone(),
+                            kind,
+                            user_token.clients.clone(),
+                            user_token.froms.clone(),
+                            vec![token.clone()],
+                        )
+                    });
+                    self.user_tokens.insert(token.clone(), user_token);
+                    event
+                }
+                None => None,
+            },
+        };
+        let was_banned = event.is_some();
+        if let Some(event) = event {
+            self.notify_unban(&event);
+        }
+        was_banned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escalated_retention_is_unchanged_for_the_first_strike() {
+        let base = Duration::from_secs(10);
+        assert_eq!(escalated_retention(base, 0), base);
+        assert_eq!(escalated_retention(base, 1), base);
+    }
+
+    #[test]
+    fn escalated_retention_doubles_per_additional_strike() {
+        let base = Duration::from_secs(10);
+        assert_eq!(escalated_retention(base, 2), base * 2);
+        assert_eq!(escalated_retention(base, 3), base * 4);
+        assert_eq!(escalated_retention(base, 4), base * 8);
+    }
+
+    #[test]
+    fn escalated_retention_clamps_instead_of_overflowing() {
+        assert_eq!(escalated_retention(Duration::MAX, 5), Duration::MAX);
+    }
+
+    fn test_config() -> Config {
+        let bucket = BucketConfig {
+            base_size: 100,
+            leak_rate: 1,
+            overflow_size: 10,
+            retention: 60,
+        };
+        Config {
+            timeframe: Duration::from_secs(60),
+            incorrect_nonce_bucket: bucket,
+            max_gas_bucket: bucket,
+            revert_bucket: bucket,
+            gas_buckets: GasBucketsConfig {
+                near_gas: bucket,
+                eth_gas: bucket,
+                free_gas: bucket,
+            },
+            token_multiplier: 1,
+        }
+    }
+
+    #[test]
+    fn manual_ban_on_an_already_banned_key_preserves_strikes_and_associations() {
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let from = Address::zero();
+        let mut banhammer = Banhammer::new(test_config());
+
+        banhammer.associate_with_user_client(ip, from, None);
+        banhammer.manual_ban(BannedUserKind::Client(ip), BanKind::MaxGas);
+        banhammer.manual_ban(BannedUserKind::Client(ip), BanKind::IncorrectNonce);
+
+        let client = banhammer
+            .ban_list()
+            .clients
+            .get(&ip)
+            .expect("client should still be banned after the second manual_ban");
+        assert_eq!(client.strikes, 2);
+        assert_eq!(client.banned, Some(BanKind::IncorrectNonce));
+        assert_eq!(client.froms, vec![from]);
+    }
+
+    #[test]
+    fn manual_unban_clears_the_ban_and_returns_to_active_tracking() {
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let mut banhammer = Banhammer::new(test_config());
+
+        banhammer.manual_ban(BannedUserKind::Client(ip), BanKind::MaxGas);
+        assert!(banhammer.manual_unban(&BannedUserKind::Client(ip)));
+
+        assert!(!banhammer.ban_list().clients.contains_key(&ip));
+        assert!(!banhammer.manual_unban(&BannedUserKind::Client(ip)));
+    }
 }
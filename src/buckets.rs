@@ -1,7 +1,7 @@
 use crate::de::Token;
 use ethereum_types::Address;
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, net::IpAddr};
+use serde::{de::Deserializer, ser::Serializer, Deserialize, Serialize};
+use std::{net::IpAddr, time::Instant};
 
 #[derive(Debug, Hash, Clone)]
 pub enum BucketKind {
@@ -30,6 +30,156 @@ pub struct BucketConfig {
     pub overflow_size: u64,
     pub retention: u64,
 }
+
+impl BucketConfig {
+    /// Scales `base_size` and `overflow_size` by `multiplier`, e.g. to grant
+    /// token-associated traffic a larger allowance (see `token_multiplier`).
+    pub fn scaled(&self, multiplier: u64) -> Self {
+        Self {
+            base_size: self.base_size.saturating_mul(multiplier),
+            leak_rate: self.leak_rate,
+            overflow_size: self.overflow_size.saturating_mul(multiplier),
+            retention: self.retention,
+        }
+    }
+}
+
+/// A single per-key leaky bucket: every event adds its cost to `value`, and
+/// `value` continuously drains at `BucketConfig::leak_rate` units per second.
+/// A key is considered over its budget once `value` exceeds
+/// `base_size + overflow_size`.
+#[derive(Debug, Clone)]
+pub struct LeakyBucket {
+    value: u64,
+    last_update: Instant,
+}
+
+impl LeakyBucket {
+    pub fn new() -> Self {
+        Self {
+            value: 0,
+            last_update: Instant::now(),
+        }
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Drains the bucket for the time elapsed since the last event, adds
+    /// `cost`, and reports whether the bucket is now over `config`'s budget.
+    pub fn record(&mut self, config: &BucketConfig, cost: u64, now: Instant) -> bool {
+        let elapsed_secs = now.saturating_duration_since(self.last_update).as_secs();
+        let leaked = config.leak_rate.saturating_mul(elapsed_secs);
+        self.value = self.value.saturating_sub(leaked).saturating_add(cost);
+        // Advance only by the whole seconds leaked, so the sub-second
+        // remainder carries over and closely spaced events still drain.
+        self.last_update += std::time::Duration::from_secs(elapsed_secs);
+        self.value > config.base_size + config.overflow_size
+    }
+}
+
+impl Default for LeakyBucket {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// `Instant` has no fixed epoch and can't survive a process restart, so only
+// `value` is persisted; a reloaded bucket is treated as freshly updated.
+impl Serialize for LeakyBucket {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.value)
+    }
+}
+
+impl<'de> Deserialize<'de> for LeakyBucket {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = u64::deserialize(deserializer)?;
+        Ok(Self {
+            value,
+            last_update: Instant::now(),
+        })
+    }
+}
+
+/// Per-key gas accounting limits: `near_gas` and `eth_gas` bound cumulative
+/// gas spend, while `free_gas` gives subsidized/zero-fee transactions their
+/// own, much tighter allowance.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+pub struct GasBucketsConfig {
+    pub near_gas: BucketConfig,
+    pub eth_gas: BucketConfig,
+    pub free_gas: BucketConfig,
+}
+
+/// Live leaky buckets backing a `GasBucketsConfig`, one set per tracked key.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct GasBuckets {
+    pub near_gas: LeakyBucket,
+    pub eth_gas: LeakyBucket,
+    pub free_gas: LeakyBucket,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn config() -> BucketConfig {
+        BucketConfig {
+            base_size: 10,
+            leak_rate: 2,
+            overflow_size: 5,
+            retention: 60,
+        }
+    }
+
+    #[test]
+    fn record_accumulates_cost_and_reports_overflow() {
+        let mut bucket = LeakyBucket::new();
+        let now = Instant::now();
+
+        assert!(!bucket.record(&config(), 10, now));
+        assert_eq!(bucket.value(), 10);
+
+        assert!(bucket.record(&config(), 6, now));
+        assert_eq!(bucket.value(), 16);
+    }
+
+    #[test]
+    fn record_leaks_before_adding_the_new_cost() {
+        let mut bucket = LeakyBucket::new();
+        let now = Instant::now();
+        bucket.record(&config(), 10, now);
+
+        let later = now + Duration::from_secs(3);
+        assert!(!bucket.record(&config(), 1, later));
+        // 10 - (leak_rate 2 * 3s) + 1 = 5
+        assert_eq!(bucket.value(), 5);
+    }
+
+    #[test]
+    fn record_never_leaks_below_zero() {
+        let mut bucket = LeakyBucket::new();
+        let now = Instant::now();
+        bucket.record(&config(), 3, now);
+
+        let later = now + Duration::from_secs(100);
+        bucket.record(&config(), 0, later);
+        assert_eq!(bucket.value(), 0);
+    }
+
+    #[test]
+    fn scaled_multiplies_sizes_but_not_leak_rate_or_retention() {
+        let scaled = config().scaled(3);
+        assert_eq!(scaled.base_size, 30);
+        assert_eq!(scaled.overflow_size, 15);
+        assert_eq!(scaled.leak_rate, 2);
+        assert_eq!(scaled.retention, 60);
+    }
+}
+
 /*
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NamedBucketConfig {
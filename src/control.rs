@@ -0,0 +1,171 @@
+use crate::banhammer::{BanKind, BanProgressSnapshot, BannedUserKind, Banhammer};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    thread,
+};
+use subtle::ConstantTimeEq;
+
+/// Placeholder response sent back when a connection's first line doesn't
+/// match the server's configured token.
+const UNAUTHORIZED: &str = "unauthorized";
+
+/// A single control-plane request understood by [`ControlServer`]. Each
+/// connection sends one JSON-encoded request per line and receives one
+/// [`ControlResponse`] back.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum ControlRequest {
+    /// List every currently banned client, address and token.
+    ListBans,
+    /// Look up a key's current ban progress, banned or not.
+    Progress(BannedUserKind),
+    /// Manually ban a key, bypassing the usual bucket thresholds.
+    Ban { user: BannedUserKind, kind: BanKind },
+    /// Lift a ban, returning the key to active tracking.
+    Unban(BannedUserKind),
+}
+
+/// Response payload mirroring a [`ControlRequest`].
+#[derive(Debug, Serialize)]
+#[serde(tag = "result", content = "value", rename_all = "snake_case")]
+pub enum ControlResponse {
+    Bans(Vec<BannedUserKind>),
+    Progress(Option<BanProgressSnapshot>),
+    Banned,
+    Unbanned(bool),
+    Error(String),
+}
+
+fn handle_request(banhammer: &Mutex<Banhammer>, request: ControlRequest) -> ControlResponse {
+    let mut banhammer = match banhammer.lock() {
+        Ok(guard) => guard,
+        Err(_) => return ControlResponse::Error("banhammer lock poisoned".to_string()),
+    };
+    match request {
+        ControlRequest::ListBans => ControlResponse::Bans(banhammer.banned_users()),
+        ControlRequest::Progress(user) => ControlResponse::Progress(banhammer.progress(&user)),
+        ControlRequest::Ban { user, kind } => {
+            banhammer.manual_ban(user, kind);
+            ControlResponse::Banned
+        }
+        ControlRequest::Unban(user) => ControlResponse::Unbanned(banhammer.manual_unban(&user)),
+    }
+}
+
+/// A small JSON-RPC-style control surface over a running [`Banhammer`]: lets
+/// an operator tool enumerate bans, inspect a key's progress, or surgically
+/// ban/unban a client, address or token without restarting the relayer.
+///
+/// Every connection must authenticate before sending requests: its first
+/// line must be the server's shared `token`, verbatim. This is a write-
+/// capable socket that can ban or unban arbitrary keys, so it is never
+/// served unauthenticated.
+pub struct ControlServer {
+    listener: TcpListener,
+    banhammer: Arc<Mutex<Banhammer>>,
+    token: Arc<str>,
+}
+
+impl ControlServer {
+    pub fn bind<A: ToSocketAddrs>(
+        addr: A,
+        banhammer: Arc<Mutex<Banhammer>>,
+        token: impl Into<Arc<str>>,
+    ) -> std::io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            banhammer,
+            token: token.into(),
+        })
+    }
+
+    /// Serves control connections until the process exits. Each connection is
+    /// handled on its own thread and may send any number of requests, one per
+    /// line, after authenticating with the shared token.
+    pub fn serve(self) -> std::io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let banhammer = Arc::clone(&self.banhammer);
+            let token = Arc::clone(&self.token);
+            thread::spawn(move || {
+                if let Err(err) = Self::handle_connection(stream, banhammer, &token) {
+                    eprintln!("control connection error: {}", err);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn handle_connection(
+        stream: TcpStream,
+        banhammer: Arc<Mutex<Banhammer>>,
+        token: &str,
+    ) -> std::io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        let reader = BufReader::new(stream);
+        let mut lines = reader.lines();
+
+        let authenticated = matches!(
+            lines.next(),
+            Some(Ok(line)) if Self::tokens_match(&line, token)
+        );
+        if !authenticated {
+            Self::write_response(&mut writer, &ControlResponse::Error(UNAUTHORIZED.to_string()))?;
+            return Ok(());
+        }
+
+        for line in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<ControlRequest>(&line) {
+                Ok(request) => handle_request(&banhammer, request),
+                Err(err) => ControlResponse::Error(err.to_string()),
+            };
+            Self::write_response(&mut writer, &response)?;
+        }
+        Ok(())
+    }
+
+    /// Compares `line` against the expected `token` in constant time, so a
+    /// timing side channel can't be used to guess the shared secret byte by
+    /// byte. Falls back to `false` on a length mismatch, since `ConstantTimeEq`
+    /// only compares equal-length slices and leaking the token's length isn't
+    /// a meaningful risk here.
+    fn tokens_match(line: &str, token: &str) -> bool {
+        let (line, token) = (line.as_bytes(), token.as_bytes());
+        line.len() == token.len() && bool::from(line.ct_eq(token))
+    }
+
+    fn write_response(writer: &mut TcpStream, response: &ControlResponse) -> std::io::Result<()> {
+        let mut payload = serde_json::to_string(response)
+            .unwrap_or_else(|_| "{\"result\":\"error\"}".to_string());
+        payload.push('\n');
+        writer.write_all(payload.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_match_accepts_the_exact_token() {
+        assert!(ControlServer::tokens_match("s3cr3t", "s3cr3t"));
+    }
+
+    #[test]
+    fn tokens_match_rejects_a_wrong_token() {
+        assert!(!ControlServer::tokens_match("s3cr3t", "wrong"));
+    }
+
+    #[test]
+    fn tokens_match_rejects_a_token_of_different_length() {
+        assert!(!ControlServer::tokens_match("s3cr3t", "s3cr3t!"));
+        assert!(!ControlServer::tokens_match("short", "much-longer-token"));
+    }
+}
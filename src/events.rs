@@ -0,0 +1,189 @@
+use crate::banhammer::{BanKind, BannedUserKind};
+use crate::de::Token;
+use ethereum_types::Address;
+use serde::Serialize;
+use std::{
+    io::Write,
+    net::{IpAddr, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Sender},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Whether a `BanEvent` reports a ban taking effect or being lifted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BanTransition {
+    Banned,
+    Unbanned,
+}
+
+/// A single ban or unban transition, carrying everything a downstream
+/// consumer needs without querying `Banhammer` back: which key changed, why,
+/// every IP/address/token associated with it, and when.
+#[derive(Debug, Clone, Serialize)]
+pub struct BanEvent {
+    pub transition: BanTransition,
+    pub user: BannedUserKind,
+    pub kind: BanKind,
+    pub clients: Vec<IpAddr>,
+    pub froms: Vec<Address>,
+    pub tokens: Vec<Token>,
+    pub timestamp_secs: u64,
+}
+
+impl BanEvent {
+    pub fn new(
+        transition: BanTransition,
+        user: BannedUserKind,
+        kind: BanKind,
+        clients: Vec<IpAddr>,
+        froms: Vec<Address>,
+        tokens: Vec<Token>,
+    ) -> Self {
+        Self {
+            transition,
+            user,
+            kind,
+            clients,
+            froms,
+            tokens,
+            timestamp_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        }
+    }
+}
+
+/// A destination for ban transitions. Implementations are free to log,
+/// alert, or push the event into firewall automation; `Banhammer` just fans
+/// each transition out to every registered sink.
+pub trait BanEventSink: Send + Sync {
+    fn on_ban(&self, event: &BanEvent);
+    fn on_unban(&self, event: &BanEvent);
+}
+
+/// Bound on how long a single delivery may spend connecting or writing,
+/// so one unreachable/slow webhook endpoint can't stall delivery of every
+/// later event.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Built-in sink that POSTs each event's JSON encoding to a configured HTTP
+/// endpoint, so downstream alerting/firewall automation can react in real
+/// time. Speaks raw HTTP/1.1 over a `TcpStream` rather than pulling in an
+/// HTTP client dependency.
+///
+/// `on_ban`/`on_unban` only hand the event to a background thread over a
+/// channel; the actual connect/write happens off `Banhammer::read_input`'s
+/// calling thread, so a slow or unreachable endpoint can't stall transaction
+/// processing.
+pub struct WebhookSink {
+    sender: Sender<BanEvent>,
+}
+
+impl WebhookSink {
+    pub fn new(host: impl Into<String>, port: u16, path: impl Into<String>) -> Self {
+        let host = host.into();
+        let path = path.into();
+        let (sender, receiver) = mpsc::channel::<BanEvent>();
+        thread::spawn(move || {
+            for event in receiver {
+                Self::post(&host, port, &path, &event);
+            }
+        });
+        Self { sender }
+    }
+
+    fn post(host: &str, port: u16, path: &str, event: &BanEvent) {
+        let body = match serde_json::to_vec(event) {
+            Ok(body) => body,
+            Err(err) => {
+                eprintln!("failed to serialize ban event: {}", err);
+                return;
+            }
+        };
+        let request = Self::format_request(path, host, body.len());
+
+        let addr = match (host, port).to_socket_addrs().ok().and_then(|mut addrs| addrs.next()) {
+            Some(addr) => addr,
+            None => {
+                eprintln!("failed to resolve webhook address {}:{}", host, port);
+                return;
+            }
+        };
+
+        match TcpStream::connect_timeout(&addr, WEBHOOK_TIMEOUT) {
+            Ok(mut stream) => {
+                if let Err(err) = stream.set_write_timeout(Some(WEBHOOK_TIMEOUT)) {
+                    eprintln!("failed to set webhook write timeout: {}", err);
+                }
+                let sent = stream
+                    .write_all(request.as_bytes())
+                    .and_then(|_| stream.write_all(&body));
+                if let Err(err) = sent {
+                    eprintln!("failed to POST ban event to {}:{}: {}", host, port, err);
+                }
+            }
+            Err(err) => eprintln!("failed to connect to webhook {}:{}: {}", host, port, err),
+        }
+    }
+
+    /// Builds the HTTP/1.1 request line and headers sent ahead of `body`,
+    /// split out from `post` so the formatting can be unit tested without
+    /// opening a socket.
+    fn format_request(path: &str, host: &str, body_len: usize) -> String {
+        format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            path, host, body_len
+        )
+    }
+}
+
+impl BanEventSink for WebhookSink {
+    fn on_ban(&self, event: &BanEvent) {
+        let _ = self.sender.send(event.clone());
+    }
+
+    fn on_unban(&self, event: &BanEvent) {
+        let _ = self.sender.send(event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_event() -> BanEvent {
+        BanEvent::new(
+            BanTransition::Banned,
+            BannedUserKind::Client("127.0.0.1".parse().unwrap()),
+            BanKind::MaxGas,
+            vec!["127.0.0.1".parse().unwrap()],
+            vec![Address::zero()],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn format_request_includes_the_path_host_and_body_length() {
+        let request = WebhookSink::format_request("/hooks/bans", "example.test", 42);
+
+        assert!(request.starts_with("POST /hooks/bans HTTP/1.1\r\n"));
+        assert!(request.contains("Host: example.test\r\n"));
+        assert!(request.contains("Content-Type: application/json\r\n"));
+        assert!(request.contains("Content-Length: 42\r\n"));
+        assert!(request.ends_with("Connection: close\r\n\r\n"));
+    }
+
+    #[test]
+    fn ban_event_serializes_transition_and_kind_as_json() {
+        let event = sample_event();
+        let body = serde_json::to_vec(&event).expect("ban event should serialize");
+        let value: serde_json::Value =
+            serde_json::from_slice(&body).expect("serialized ban event should parse as json");
+
+        assert_eq!(value["transition"], "banned");
+        assert_eq!(value["kind"], "MaxGas");
+    }
+}
@@ -0,0 +1,244 @@
+use crate::banhammer::BanhammerState;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// On-disk snapshot format version. Bump whenever `BanhammerState`'s shape
+/// changes in a way that isn't backward compatible.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+struct SnapshotFileRef<'a> {
+    version: u32,
+    /// Byte length of the serialized `state`, re-checked on load so a
+    /// truncated write is rejected instead of silently misparsed.
+    length: usize,
+    state: &'a BanhammerState,
+}
+
+#[derive(Debug, Deserialize)]
+struct SnapshotFileOwned {
+    version: u32,
+    length: usize,
+    state: BanhammerState,
+}
+
+/// Periodically serializes `Banhammer` state to disk and reloads it on
+/// startup, so a relayer restart doesn't wipe every ban and in-flight
+/// progress counter.
+pub struct SnapshotStore {
+    path: PathBuf,
+    /// Fingerprints of snapshots that failed to validate, so a known-bad file
+    /// is not retried on every load.
+    known_bad: HashSet<String>,
+}
+
+impl SnapshotStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            known_bad: HashSet::new(),
+        }
+    }
+
+    /// Loads and validates the snapshot at `path`, falling back to empty
+    /// state if it is missing, malformed, or already known bad. Never panics
+    /// on a corrupt snapshot.
+    pub fn load(&mut self) -> BanhammerState {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(_) => return BanhammerState::default(),
+        };
+
+        let identifier = Self::identifier(&self.path, &bytes);
+        if self.known_bad.contains(&identifier) {
+            return BanhammerState::default();
+        }
+
+        match Self::validate(&bytes) {
+            Ok(state) => state,
+            Err(reason) => {
+                eprintln!(
+                    "rejecting corrupt snapshot {} ({}): {}",
+                    self.path.display(),
+                    identifier,
+                    reason
+                );
+                self.known_bad.insert(identifier);
+                BanhammerState::default()
+            }
+        }
+    }
+
+    /// Serializes `state` to `path`, recording its own length for later
+    /// validation.
+    pub fn save(&self, state: &BanhammerState) -> io::Result<()> {
+        let length = serde_json::to_vec(state)?.len();
+        let file = SnapshotFileRef {
+            version: SNAPSHOT_VERSION,
+            length,
+            state,
+        };
+        let bytes = serde_json::to_vec_pretty(&file)?;
+        fs::write(&self.path, bytes)
+    }
+
+    fn validate(bytes: &[u8]) -> Result<BanhammerState, String> {
+        let file: SnapshotFileOwned =
+            serde_json::from_slice(bytes).map_err(|err| format!("invalid snapshot json: {}", err))?;
+
+        if file.version != SNAPSHOT_VERSION {
+            return Err(format!(
+                "unsupported snapshot version {} (expected {})",
+                file.version, SNAPSHOT_VERSION
+            ));
+        }
+
+        let measured_length = serde_json::to_vec(&file.state)
+            .map_err(|err| format!("failed to re-measure snapshot: {}", err))?
+            .len();
+        if measured_length != file.length {
+            return Err(format!(
+                "snapshot length mismatch: recorded {}, measured {}",
+                file.length, measured_length
+            ));
+        }
+
+        Ok(file.state)
+    }
+
+    /// Cheap fingerprint identifying a specific snapshot file's contents, so
+    /// the same bad file isn't retried while a later rewrite with different
+    /// contents is.
+    fn identifier(path: &Path, bytes: &[u8]) -> String {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        format!("{}:{}:{:016x}", path.display(), bytes.len(), hasher.finish())
+    }
+}
+
+/// Drives `SnapshotStore` on an interval: loads once at startup and then
+/// saves the latest state every time at least `interval` has elapsed since
+/// the previous save, so `Banhammer::tick` can call `maybe_save` on every
+/// pass without writing to disk on every transaction.
+pub struct SnapshotSchedule {
+    store: SnapshotStore,
+    interval: Duration,
+    last_save: Instant,
+}
+
+impl SnapshotSchedule {
+    pub fn new(path: impl Into<PathBuf>, interval: Duration) -> Self {
+        Self {
+            store: SnapshotStore::new(path),
+            interval,
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Loads and validates the snapshot, exactly like `SnapshotStore::load`.
+    pub fn load(&mut self) -> BanhammerState {
+        self.store.load()
+    }
+
+    /// Saves `state` if `interval` has elapsed since the last save. I/O
+    /// errors are logged rather than propagated, so a failed write never
+    /// interrupts transaction processing.
+    pub fn maybe_save(&mut self, state: &BanhammerState, now: Instant) {
+        if now.saturating_duration_since(self.last_save) < self.interval {
+            return;
+        }
+        self.last_save = now;
+        if let Err(err) = self.store.save(state) {
+            eprintln!(
+                "failed to save snapshot to {}: {}",
+                self.store.path.display(),
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_bytes(version: u32, length: usize, state: &BanhammerState) -> Vec<u8> {
+        let file = SnapshotFileRef {
+            version,
+            length,
+            state,
+        };
+        serde_json::to_vec_pretty(&file).unwrap()
+    }
+
+    #[test]
+    fn validate_accepts_a_freshly_saved_snapshot() {
+        let state = BanhammerState::default();
+        let length = serde_json::to_vec(&state).unwrap().len();
+        let bytes = file_bytes(SNAPSHOT_VERSION, length, &state);
+        assert!(SnapshotStore::validate(&bytes).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_an_unsupported_version() {
+        let state = BanhammerState::default();
+        let length = serde_json::to_vec(&state).unwrap().len();
+        let bytes = file_bytes(SNAPSHOT_VERSION + 1, length, &state);
+        assert!(SnapshotStore::validate(&bytes).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_a_truncated_state() {
+        let state = BanhammerState::default();
+        let bytes = file_bytes(SNAPSHOT_VERSION, 0, &state);
+        assert!(SnapshotStore::validate(&bytes).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_garbage_bytes() {
+        assert!(SnapshotStore::validate(b"not json").is_err());
+    }
+
+    #[test]
+    fn save_then_load_round_trips_state() {
+        let path = std::env::temp_dir().join(format!(
+            "banhammer-snapshot-test-{}.json",
+            std::process::id()
+        ));
+        let mut store = SnapshotStore::new(path.clone());
+        let state = BanhammerState::default();
+
+        store.save(&state).unwrap();
+        let loaded = store.load();
+
+        assert_eq!(
+            serde_json::to_string(&loaded).unwrap(),
+            serde_json::to_string(&state).unwrap()
+        );
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn load_rejects_a_corrupt_file_without_panicking() {
+        let path = std::env::temp_dir().join(format!(
+            "banhammer-snapshot-test-corrupt-{}.json",
+            std::process::id()
+        ));
+        fs::write(&path, b"not json").unwrap();
+
+        let mut store = SnapshotStore::new(path.clone());
+        let loaded = store.load();
+
+        assert_eq!(
+            serde_json::to_string(&loaded).unwrap(),
+            serde_json::to_string(&BanhammerState::default()).unwrap()
+        );
+        let _ = fs::remove_file(&path);
+    }
+}